@@ -22,6 +22,12 @@ impl From<usize> for NodeId {
     }
 }
 
+impl NodeId {
+    pub(crate) fn index(&self) -> usize {
+        self.0
+    }
+}
+
 /// A node, representing a side effect of a page load.
 #[derive(Debug)]
 pub struct Node {
@@ -39,6 +45,12 @@ impl From<usize> for EdgeId {
     }
 }
 
+impl EdgeId {
+    pub(crate) fn index(&self) -> usize {
+        self.0
+    }
+}
+
 /// An edge, representing an action taken during page load.
 #[derive(Debug)]
 pub struct Edge {
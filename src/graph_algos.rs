@@ -1,3 +1,5 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
 use crate::graph::{Edge, EdgeId, Node, NodeId, PageGraph};
 use crate::types::{EdgeType, NodeType};
 
@@ -330,24 +332,354 @@ impl PageGraph {
     }
 
     pub fn all_downstream_effects_of(&self, init_node_id: NodeId) -> Vec<NodeRef> {
-        let mut nodes_to_check: Vec<NodeId> = vec![init_node_id];
-        let mut already_checked: Vec<NodeId> = vec![];
+        let (visited, _) = bfs(init_node_id, |node_id| {
+            self.direct_downstream_effects_of(node_id)
+                .into_iter()
+                .map(|(inner_node_id, _)| (inner_node_id, ()))
+                .collect()
+        });
+
+        visited
+            .into_iter()
+            .filter(|node_id| *node_id != init_node_id)
+            .map(|node_id| (node_id, self.nodes.get(&node_id).unwrap()))
+            .collect()
+    }
+
+    /// Returns `true` if `to` is reachable from `from` by following only edges whose `EdgeType`
+    /// satisfies `edge_pred`.
+    pub fn can_reach<F: Fn(&EdgeType) -> bool>(&self, from: NodeId, to: NodeId, edge_pred: F) -> bool {
+        self.causal_path(from, to, edge_pred).is_some()
+    }
+
+    /// Finds the shortest (fewest-hop) causal path from `from` to `to`, following only edges
+    /// whose `EdgeType` satisfies `edge_pred`. Returns `None` if `to` is not reachable this way.
+    ///
+    /// The returned path is a sequence of `(NodeId, Option<EdgeId>)` pairs, one per node visited
+    /// in order, where the `EdgeId` is the edge used to arrive at that node (and is `None` for
+    /// `from` itself).
+    pub fn causal_path<F: Fn(&EdgeType) -> bool>(
+        &self,
+        from: NodeId,
+        to: NodeId,
+        edge_pred: F,
+    ) -> Option<Vec<(NodeId, Option<EdgeId>)>> {
+        let (visited, predecessor) = bfs(from, |node_id| {
+            self.graph
+                .edges_directed(node_id, Direction::Outgoing)
+                .filter_map(|(_src, neighbor, edge_id)| {
+                    let edge_type = &self
+                        .edges
+                        .get(edge_id)
+                        .expect("dangling edge id in graph")
+                        .edge_type;
+                    if edge_pred(edge_type) {
+                        Some((neighbor, *edge_id))
+                    } else {
+                        None
+                    }
+                })
+                .collect()
+        });
+
+        if !visited.contains(&to) {
+            return None;
+        }
+
+        Some(reconstruct_path(from, to, &predecessor))
+    }
 
-        while let Some(node_id) = nodes_to_check.pop() {
-            let direct_effects = self.direct_downstream_effects_of(node_id);
-            already_checked.push(node_id);
+    /// Like `causal_path`, but among all causal paths from `from` to `to` returns the one that
+    /// completes earliest, treating each edge as only traversable at its own `edge_timestamp`
+    /// (edges with no timestamp are never traversed). This is the "was this tracker request
+    /// reachable, and how soon" variant of `causal_path`.
+    pub fn earliest_causal_path<F: Fn(&EdgeType) -> bool>(
+        &self,
+        from: NodeId,
+        to: NodeId,
+        edge_pred: F,
+    ) -> Option<Vec<(NodeId, Option<EdgeId>)>> {
+        let mut eligible_edges: Vec<(NodeId, NodeId, EdgeId, isize)> = self
+            .graph
+            .all_edges()
+            .filter_map(|(src, dst, edge_id)| {
+                let edge = self.edges.get(edge_id).expect("dangling edge id in graph");
+                if !edge_pred(&edge.edge_type) {
+                    return None;
+                }
+                edge.edge_timestamp.map(|ts| (src, dst, *edge_id, ts))
+            })
+            .collect();
+        eligible_edges.sort_by_key(|(_src, _dst, _edge_id, ts)| *ts);
+
+        let mut arrival: HashMap<NodeId, isize> = HashMap::new();
+        let mut predecessor: HashMap<NodeId, (NodeId, EdgeId)> = HashMap::new();
+        arrival.insert(from, isize::MIN);
 
-            direct_effects.into_iter().for_each(|(inner_node_id, _)| {
-                if !already_checked.contains(&inner_node_id) {
-                    nodes_to_check.push(node_id);
+        // Edges sharing a timestamp can chain in either order, so sweep until nothing improves.
+        loop {
+            let mut improved_any = false;
+            for &(src, dst, edge_id, ts) in &eligible_edges {
+                let src_arrival = match arrival.get(&src) {
+                    Some(t) => *t,
+                    None => continue,
+                };
+                if ts < src_arrival {
+                    continue;
                 }
-            });
+
+                let improves = match arrival.get(&dst) {
+                    Some(existing_arrival) => ts < *existing_arrival,
+                    None => true,
+                };
+                if improves {
+                    arrival.insert(dst, ts);
+                    predecessor.insert(dst, (src, edge_id));
+                    improved_any = true;
+                }
+            }
+            if !improved_any {
+                break;
+            }
         }
 
-        already_checked
-            .into_iter()
-            .map(|node_id| (node_id, self.nodes.get(&node_id).unwrap()))
-            .collect()
+        if !arrival.contains_key(&to) {
+            return None;
+        }
+
+        Some(reconstruct_path(from, to, &predecessor))
+    }
+}
+
+/// Breadth-first traversal of a relation over `NodeId`s, starting at `init` and expanding each
+/// node via `next`, which returns each reachable neighbor paired with the label (e.g. an
+/// `EdgeId`) of the step taken to reach it. Visits each node at most once. Returns the set of
+/// visited nodes together with a predecessor map sufficient to reconstruct the shortest (by hop
+/// count) path from `init` to any visited node.
+fn bfs<T: Copy, F: FnMut(NodeId) -> Vec<(NodeId, T)>>(
+    init: NodeId,
+    mut next: F,
+) -> (HashSet<NodeId>, HashMap<NodeId, (NodeId, T)>) {
+    let mut visited = HashSet::new();
+    let mut predecessor = HashMap::new();
+    let mut queue = VecDeque::new();
+
+    visited.insert(init);
+    queue.push_back(init);
+
+    while let Some(node_id) = queue.pop_front() {
+        for (neighbor, label) in next(node_id) {
+            if visited.insert(neighbor) {
+                predecessor.insert(neighbor, (node_id, label));
+                queue.push_back(neighbor);
+            }
+        }
+    }
+
+    (visited, predecessor)
+}
+
+/// Walks a predecessor map backwards from `to` to `from`, reconstructing the path as a
+/// chronological sequence of `(NodeId, Option<EdgeId>)` pairs.
+fn reconstruct_path(
+    from: NodeId,
+    to: NodeId,
+    predecessor: &HashMap<NodeId, (NodeId, EdgeId)>,
+) -> Vec<(NodeId, Option<EdgeId>)> {
+    let mut path = Vec::new();
+    let mut current = to;
+
+    loop {
+        let edge_used = predecessor.get(&current).map(|(_prev, edge_id)| *edge_id);
+        path.push((current, edge_used));
+        if current == from {
+            break;
+        }
+        current = predecessor[&current].0;
+    }
+
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+#[path = "graph_test_fixtures.rs"]
+mod fixtures;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use super::fixtures::sample_graph;
+
+    // Under the previous implementation, all_downstream_effects_of pushed the node it had just
+    // popped back onto the work queue instead of the newly-discovered effect, so any node with a
+    // non-empty direct_downstream_effects_of() (even without a cycle, as here) looped forever
+    // instead of terminating.
+    #[test]
+    fn all_downstream_effects_of_terminates_and_finds_the_one_effect() {
+        let g = sample_graph();
+
+        let effects = g.all_downstream_effects_of(NodeId::from(0));
+
+        assert_eq!(effects.len(), 1);
+        assert_eq!(effects[0].0, NodeId::from(1));
+    }
+
+    /// Builds a graph with a 1-hop path from `a` to `d` whose edge is excluded by the test
+    /// predicate below, and a 3-hop path through `b` and `c` whose edges are allowed by it.
+    fn diamond_graph() -> (PageGraph, NodeId, NodeId, NodeId, NodeId) {
+        let a = NodeId::from(0);
+        let b = NodeId::from(1);
+        let c = NodeId::from(2);
+        let d = NodeId::from(3);
+
+        let mut nodes = HashMap::new();
+        for (id, html_node_id) in [(a, 1), (b, 2), (c, 3), (d, 4)] {
+            nodes.insert(
+                id,
+                Node {
+                    node_timestamp: 0,
+                    node_type: NodeType::HtmlElement {
+                        node_id: html_node_id,
+                        tag_name: "div".to_string(),
+                        is_deleted: false,
+                    },
+                },
+            );
+        }
+
+        let mut edges = HashMap::new();
+        let mut graph = petgraph::graphmap::DiGraphMap::new();
+        for id in [a, b, c, d] {
+            graph.add_node(id);
+        }
+
+        let mut add_edge = |from, to, edge_id: EdgeId, request_type: &str| {
+            edges.insert(
+                edge_id,
+                Edge {
+                    edge_timestamp: Some(0),
+                    edge_type: EdgeType::RequestStart {
+                        request_type: request_type.to_string(),
+                    },
+                },
+            );
+            graph.add_edge(from, to, edge_id);
+        };
+        add_edge(a, d, EdgeId::from(0), "blocked");
+        add_edge(a, b, EdgeId::from(1), "allowed");
+        add_edge(b, c, EdgeId::from(2), "allowed");
+        add_edge(c, d, EdgeId::from(3), "allowed");
+
+        (PageGraph { edges, nodes, graph }, a, b, c, d)
+    }
+
+    fn allowed(edge_type: &EdgeType) -> bool {
+        matches!(edge_type, EdgeType::RequestStart { request_type } if request_type == "allowed")
+    }
+
+    #[test]
+    fn causal_path_prunes_edges_excluded_by_the_predicate() {
+        let (g, a, b, c, d) = diamond_graph();
+
+        let path = g.causal_path(a, d, allowed).expect("d is reachable from a via b, c");
+        let visited: Vec<NodeId> = path.iter().map(|(node_id, _)| *node_id).collect();
+
+        assert_eq!(visited, vec![a, b, c, d]);
+    }
+
+    #[test]
+    fn can_reach_is_true_when_an_allowed_path_exists() {
+        let (g, a, _b, _c, d) = diamond_graph();
+
+        assert!(g.can_reach(a, d, allowed));
+    }
+
+    #[test]
+    fn can_reach_is_false_for_an_unreachable_target() {
+        let (g, a, _b, _c, d) = diamond_graph();
+
+        // No edges run from d back to a.
+        assert!(!g.can_reach(d, a, allowed));
+        assert!(g.causal_path(d, a, allowed).is_none());
+    }
+
+    /// A graph with two timestamped routes from `a` to `d` (one through `b` that arrives late,
+    /// one through `c` that arrives early) plus an edge to `e` with no timestamp at all.
+    fn timestamped_diamond_graph() -> (PageGraph, NodeId, NodeId) {
+        let a = NodeId::from(0);
+        let b = NodeId::from(1);
+        let c = NodeId::from(2);
+        let d = NodeId::from(3);
+        let e = NodeId::from(4);
+
+        let mut nodes = HashMap::new();
+        for (id, html_node_id) in [(a, 1), (b, 2), (c, 3), (d, 4), (e, 5)] {
+            nodes.insert(
+                id,
+                Node {
+                    node_timestamp: 0,
+                    node_type: NodeType::HtmlElement {
+                        node_id: html_node_id,
+                        tag_name: "div".to_string(),
+                        is_deleted: false,
+                    },
+                },
+            );
+        }
+
+        let mut edges = HashMap::new();
+        let mut graph = petgraph::graphmap::DiGraphMap::new();
+        for id in [a, b, c, d, e] {
+            graph.add_node(id);
+        }
+
+        let mut add_edge = |from, to, edge_id: EdgeId, edge_timestamp: Option<isize>| {
+            edges.insert(
+                edge_id,
+                Edge {
+                    edge_timestamp,
+                    edge_type: EdgeType::RequestStart {
+                        request_type: "allowed".to_string(),
+                    },
+                },
+            );
+            graph.add_edge(from, to, edge_id);
+        };
+        // Arrives at d at time 10, via b.
+        add_edge(a, b, EdgeId::from(0), Some(5));
+        add_edge(b, d, EdgeId::from(1), Some(10));
+        // Arrives at d at time 3, via c -- the earliest route.
+        add_edge(a, c, EdgeId::from(2), Some(1));
+        add_edge(c, d, EdgeId::from(3), Some(3));
+        // Only reachable by an edge with no timestamp, so never traversable.
+        add_edge(a, e, EdgeId::from(4), None);
+
+        (PageGraph { edges, nodes, graph }, a, d)
+    }
+
+    #[test]
+    fn earliest_causal_path_picks_the_route_that_completes_first() {
+        let (g, a, d) = timestamped_diamond_graph();
+        let b = NodeId::from(1);
+        let c = NodeId::from(2);
+
+        let path = g
+            .earliest_causal_path(a, d, allowed)
+            .expect("d is reachable from a via b and via c");
+        let visited: Vec<NodeId> = path.iter().map(|(node_id, _)| *node_id).collect();
+
+        assert_eq!(visited, vec![a, c, d]);
+        assert!(!visited.contains(&b), "should not take the later-arriving route through b");
+    }
+
+    #[test]
+    fn earliest_causal_path_never_traverses_an_untimestamped_edge() {
+        let (g, a, _d) = timestamped_diamond_graph();
+        let e = NodeId::from(4);
+
+        assert!(g.earliest_causal_path(a, e, allowed).is_none());
     }
 }
 
@@ -0,0 +1,566 @@
+//! Structural diffing between two `PageGraph`s.
+//!
+//! `NodeId`/`EdgeId` are only meaningful within the recording that produced them, so nodes are
+//! matched across the two graphs by a content signature instead: a hash of a node's `NodeType`
+//! and salient fields, combined with the multiset of its incident `EdgeType`s. Where multiple
+//! nodes share a signature, a VF2-style alignment disambiguates them by local neighborhood.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use crate::graph::{EdgeId, NodeId, PageGraph};
+use crate::graph_dot::{edge_label, node_label, variant_name};
+use crate::types::NodeType;
+
+use petgraph::Direction;
+
+/// A stable content fingerprint for a node: its `NodeType` discriminant and salient fields
+/// combined with the multiset of its incident `EdgeType`s. Nodes with the same signature in two
+/// different `PageGraph`s are considered the same side effect for the purposes of `diff`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeSignature(u64);
+
+/// A node on one side of a `GraphDiff`, labeled the same way `to_dot` labels it.
+#[derive(Debug, Clone)]
+pub struct DiffNode {
+    pub node_id: NodeId,
+    pub signature: NodeSignature,
+    pub label: String,
+}
+
+/// An edge on one side of a `GraphDiff`, labeled the same way `to_dot` labels it.
+#[derive(Debug, Clone)]
+pub struct DiffEdge {
+    pub edge_id: EdgeId,
+    pub label: String,
+}
+
+/// The structural difference between two `PageGraph`s, as produced by `PageGraph::diff`.
+#[derive(Debug, Clone, Default)]
+pub struct GraphDiff {
+    /// Nodes present in the left-hand graph with no corresponding node in the right-hand graph.
+    pub removed_nodes: Vec<DiffNode>,
+    /// Nodes present in the right-hand graph with no corresponding node in the left-hand graph.
+    pub added_nodes: Vec<DiffNode>,
+    /// Edges present in the left-hand graph with no corresponding edge in the right-hand graph.
+    pub removed_edges: Vec<DiffEdge>,
+    /// Edges present in the right-hand graph with no corresponding edge in the left-hand graph.
+    pub added_edges: Vec<DiffEdge>,
+    /// Nodes matched across both graphs (by signature and neighborhood) whose multiset of
+    /// incident edge types differs between the two recordings, as (left, right) pairs.
+    pub changed_nodes: Vec<(DiffNode, DiffNode)>,
+}
+
+impl fmt::Display for GraphDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "- {} node(s), + {} node(s), ~ {} node(s) changed", self.removed_nodes.len(), self.added_nodes.len(), self.changed_nodes.len())?;
+        for node in &self.removed_nodes {
+            writeln!(f, "  - {}", node.label)?;
+        }
+        for node in &self.added_nodes {
+            writeln!(f, "  + {}", node.label)?;
+        }
+        for (before, after) in &self.changed_nodes {
+            writeln!(f, "  ~ {} -> {}", before.label, after.label)?;
+        }
+        writeln!(f, "- {} edge(s), + {} edge(s)", self.removed_edges.len(), self.added_edges.len())?;
+        for edge in &self.removed_edges {
+            writeln!(f, "  - {}", edge.label)?;
+        }
+        for edge in &self.added_edges {
+            writeln!(f, "  + {}", edge.label)?;
+        }
+        Ok(())
+    }
+}
+
+impl PageGraph {
+    /// Computes the structural diff between this graph and `other`: which side effects (nodes
+    /// and edges) appear on one side but not the other, and which nodes kept their identity but
+    /// picked up or lost incident edges.
+    pub fn diff(&self, other: &PageGraph) -> GraphDiff {
+        let self_signatures: HashMap<NodeId, NodeSignature> = self
+            .nodes
+            .keys()
+            .map(|&id| (id, content_signature(self, id)))
+            .collect();
+        let other_signatures: HashMap<NodeId, NodeSignature> = other
+            .nodes
+            .keys()
+            .map(|&id| (id, content_signature(other, id)))
+            .collect();
+
+        let mut matched: HashMap<NodeId, NodeId> = HashMap::new();
+        let mut matched_other: HashSet<NodeId> = HashSet::new();
+        let mut changed: HashSet<NodeId> = HashSet::new();
+
+        // Pass 1: matches where the full content signature (type, fields, and incident edge
+        // types) agrees exactly. A signature held by exactly one node on each side is applied
+        // directly. A signature shared by more than one node on either side (e.g. two
+        // interchangeable `<div>`s with the same tag and incident edge types) can't be told
+        // apart by signature alone, so those groups are aligned the same VF2-style,
+        // neighborhood-agreement way pass 2 disambiguates identity-only collisions — using
+        // whichever matches are already anchored by this point. Everything here is processed in
+        // sorted order so that collisions (and the greedy alignment's effect on later groups'
+        // neighborhood scores) are resolved deterministically rather than depending on `HashMap`
+        // iteration order.
+        let mut by_full_self: HashMap<NodeSignature, Vec<NodeId>> = HashMap::new();
+        for (&id, &sig) in &self_signatures {
+            by_full_self.entry(sig).or_default().push(id);
+        }
+        let mut by_full_other: HashMap<NodeSignature, Vec<NodeId>> = HashMap::new();
+        for (&id, &sig) in &other_signatures {
+            by_full_other.entry(sig).or_default().push(id);
+        }
+        let mut full_sigs: Vec<NodeSignature> = by_full_self.keys().copied().collect();
+        full_sigs.sort_by_key(|sig| sig.0);
+
+        let mut ambiguous_full_sigs = Vec::new();
+        for &sig in &full_sigs {
+            let self_ids = &by_full_self[&sig];
+            if let Some(other_ids) = by_full_other.get(&sig) {
+                if self_ids.len() == 1 && other_ids.len() == 1 {
+                    matched.insert(self_ids[0], other_ids[0]);
+                    matched_other.insert(other_ids[0]);
+                } else {
+                    ambiguous_full_sigs.push(sig);
+                }
+            }
+        }
+        for sig in ambiguous_full_sigs {
+            let mut self_ids = by_full_self[&sig].clone();
+            self_ids.sort();
+            let mut other_ids = by_full_other[&sig].clone();
+            other_ids.sort();
+
+            // Matching on the full signature already means these nodes' incident edges agree
+            // exactly between recordings, so (unlike pass 2) the pairing isn't a "changed" node.
+            align_by_neighborhood(self, other, self_ids, other_ids, &mut matched, &mut matched_other);
+        }
+
+        // Pass 2: among the nodes left over, group by identity alone (type and fields, ignoring
+        // incident edges) and align candidates the same way.
+        let mut by_identity_self: HashMap<u64, Vec<NodeId>> = HashMap::new();
+        for &id in self.nodes.keys() {
+            if !matched.contains_key(&id) {
+                by_identity_self.entry(identity_key(self, id)).or_default().push(id);
+            }
+        }
+        let mut by_identity_other: HashMap<u64, Vec<NodeId>> = HashMap::new();
+        for &id in other.nodes.keys() {
+            if !matched_other.contains(&id) {
+                by_identity_other.entry(identity_key(other, id)).or_default().push(id);
+            }
+        }
+        let mut identities: Vec<u64> = by_identity_self.keys().copied().collect();
+        identities.sort();
+
+        for identity in identities {
+            let mut self_ids = by_identity_self[&identity].clone();
+            self_ids.sort();
+            let other_ids = match by_identity_other.get(&identity) {
+                Some(ids) => {
+                    let mut ids = ids.clone();
+                    ids.sort();
+                    ids
+                }
+                None => continue,
+            };
+
+            let newly_matched = align_by_neighborhood(self, other, self_ids, other_ids, &mut matched, &mut matched_other);
+            changed.extend(newly_matched);
+        }
+
+        let mut removed_nodes: Vec<NodeId> = self.nodes.keys().filter(|id| !matched.contains_key(id)).copied().collect();
+        removed_nodes.sort();
+        let removed_nodes = removed_nodes
+            .into_iter()
+            .map(|id| diff_node(self, id, self_signatures[&id]))
+            .collect();
+
+        let mut added_nodes: Vec<NodeId> = other.nodes.keys().filter(|id| !matched_other.contains(id)).copied().collect();
+        added_nodes.sort();
+        let added_nodes = added_nodes
+            .into_iter()
+            .map(|id| diff_node(other, id, other_signatures[&id]))
+            .collect();
+
+        let mut changed: Vec<NodeId> = changed.into_iter().collect();
+        changed.sort();
+        let changed_nodes = changed
+            .into_iter()
+            .map(|self_id| {
+                let other_id = matched[&self_id];
+                (
+                    diff_node(self, self_id, self_signatures[&self_id]),
+                    diff_node(other, other_id, other_signatures[&other_id]),
+                )
+            })
+            .collect();
+
+        let mut removed_edges: Vec<EdgeId> = self
+            .graph
+            .all_edges()
+            .filter(|(a, b, _edge_id)| !edge_survives(self, other, *a, *b, &matched))
+            .map(|(_a, _b, edge_id)| *edge_id)
+            .collect();
+        removed_edges.sort();
+        let removed_edges = removed_edges.into_iter().map(|edge_id| diff_edge(self, edge_id)).collect();
+
+        let reverse_matched: HashMap<NodeId, NodeId> = matched.iter().map(|(&a, &b)| (b, a)).collect();
+        let mut added_edges: Vec<EdgeId> = other
+            .graph
+            .all_edges()
+            .filter(|(a, b, _edge_id)| !edge_survives(other, self, *a, *b, &reverse_matched))
+            .map(|(_a, _b, edge_id)| *edge_id)
+            .collect();
+        added_edges.sort();
+        let added_edges = added_edges.into_iter().map(|edge_id| diff_edge(other, edge_id)).collect();
+
+        GraphDiff {
+            removed_nodes,
+            added_nodes,
+            removed_edges,
+            added_edges,
+            changed_nodes,
+        }
+    }
+}
+
+fn diff_node(graph: &PageGraph, node_id: NodeId, signature: NodeSignature) -> DiffNode {
+    let node = graph.nodes.get(&node_id).expect("dangling node id in graph");
+    DiffNode {
+        node_id,
+        signature,
+        label: node_label(&node.node_type),
+    }
+}
+
+fn diff_edge(graph: &PageGraph, edge_id: EdgeId) -> DiffEdge {
+    let edge = graph.edges.get(&edge_id).expect("dangling edge id in graph");
+    DiffEdge {
+        edge_id,
+        label: edge_label(edge),
+    }
+}
+
+/// Returns `true` if `(a, b)`'s edge in `graph` has a corresponding edge, of the same `EdgeType`
+/// variant, between the matched counterparts of `a` and `b` in `other`.
+fn edge_survives(
+    graph: &PageGraph,
+    other: &PageGraph,
+    a: NodeId,
+    b: NodeId,
+    matched: &HashMap<NodeId, NodeId>,
+) -> bool {
+    let edge_id = graph.graph.edge_weight(a, b).expect("dangling edge endpoints in graph");
+    let variant = variant_name(&format!("{:?}", graph.edges.get(edge_id).unwrap().edge_type));
+
+    let (other_a, other_b) = match (matched.get(&a), matched.get(&b)) {
+        (Some(&oa), Some(&ob)) => (oa, ob),
+        _ => return false,
+    };
+
+    match other.graph.edge_weight(other_a, other_b) {
+        Some(other_edge_id) => {
+            let other_variant = variant_name(&format!("{:?}", other.edges.get(other_edge_id).unwrap().edge_type));
+            variant == other_variant
+        }
+        None => false,
+    }
+}
+
+/// Counts how many of `self_id`'s neighbors (in either direction) are already matched to one of
+/// `other_id`'s neighbors — the local-neighborhood agreement score used to disambiguate
+/// signature collisions.
+fn neighborhood_agreement(
+    self_graph: &PageGraph,
+    other_graph: &PageGraph,
+    self_id: NodeId,
+    other_id: NodeId,
+    matched: &HashMap<NodeId, NodeId>,
+) -> usize {
+    let other_neighbors = neighbors_either_direction(other_graph, other_id);
+
+    neighbors_either_direction(self_graph, self_id)
+        .into_iter()
+        .filter(|neighbor| matched.get(neighbor).map_or(false, |mapped| other_neighbors.contains(mapped)))
+        .count()
+}
+
+/// Greedily pairs off `self_ids` against `other_ids` — both groups of nodes that share a
+/// signature too coarse to tell them apart on its own — by local-neighborhood agreement with
+/// `matched`: each `self_id`, in order, is paired with whichever remaining `other_id` agrees with
+/// it the most. Registers each pairing in `matched`/`matched_other` and returns the `self_id`s
+/// that were matched, in the order they were matched (fewer than `self_ids` if `other_ids` runs
+/// out first). Callers that need deterministic results should pass pre-sorted id lists.
+fn align_by_neighborhood(
+    self_graph: &PageGraph,
+    other_graph: &PageGraph,
+    self_ids: Vec<NodeId>,
+    other_ids: Vec<NodeId>,
+    matched: &mut HashMap<NodeId, NodeId>,
+    matched_other: &mut HashSet<NodeId>,
+) -> Vec<NodeId> {
+    let mut remaining_other = other_ids;
+    let mut newly_matched = Vec::new();
+
+    for self_id in self_ids {
+        if remaining_other.is_empty() {
+            break;
+        }
+
+        let (best_index, _) = remaining_other
+            .iter()
+            .enumerate()
+            .map(|(i, &other_id)| (i, neighborhood_agreement(self_graph, other_graph, self_id, other_id, matched)))
+            .max_by_key(|(_, score)| *score)
+            .expect("remaining_other is non-empty");
+
+        let other_id = remaining_other.remove(best_index);
+        matched.insert(self_id, other_id);
+        matched_other.insert(other_id);
+        newly_matched.push(self_id);
+    }
+
+    newly_matched
+}
+
+fn neighbors_either_direction(graph: &PageGraph, node_id: NodeId) -> HashSet<NodeId> {
+    graph
+        .graph
+        .neighbors_directed(node_id, Direction::Outgoing)
+        .chain(graph.graph.neighbors_directed(node_id, Direction::Incoming))
+        .collect()
+}
+
+/// The full content signature of a node: its identity (type and salient fields) combined with
+/// the multiset of its incident edge types.
+fn content_signature(graph: &PageGraph, node_id: NodeId) -> NodeSignature {
+    let mut hasher = DefaultHasher::new();
+    identity_key(graph, node_id).hash(&mut hasher);
+    incident_edge_multiset(graph, node_id).hash(&mut hasher);
+    NodeSignature(hasher.finish())
+}
+
+/// A node's identity, ignoring its incident edges: its `NodeType` discriminant plus whichever of
+/// that variant's fields are stable across two separate recordings of the same page (tag_name,
+/// url, script source, filter rule, ...). Deliberately excludes per-recording bookkeeping like
+/// `HtmlElement`'s Blink backend node id, which differs between recordings even for the same
+/// element and would otherwise prevent almost every node from matching.
+fn identity_key(graph: &PageGraph, node_id: NodeId) -> u64 {
+    let node = graph.nodes.get(&node_id).expect("dangling node id in graph");
+    let mut hasher = DefaultHasher::new();
+
+    match &node.node_type {
+        NodeType::HtmlElement { tag_name, .. } => {
+            "HtmlElement".hash(&mut hasher);
+            tag_name.hash(&mut hasher);
+        }
+        NodeType::TextNode { text, .. } => {
+            "TextNode".hash(&mut hasher);
+            text.hash(&mut hasher);
+        }
+        NodeType::DomRoot { url, .. } => {
+            "DomRoot".hash(&mut hasher);
+            url.hash(&mut hasher);
+        }
+        NodeType::FrameOwner { tag_name, .. } => {
+            "FrameOwner".hash(&mut hasher);
+            tag_name.hash(&mut hasher);
+        }
+        NodeType::RemoteFrame { frame_id, .. } => {
+            "RemoteFrame".hash(&mut hasher);
+            frame_id.hash(&mut hasher);
+        }
+        NodeType::Resource { url } => {
+            "Resource".hash(&mut hasher);
+            url.hash(&mut hasher);
+        }
+        NodeType::Script { source, .. } => {
+            "Script".hash(&mut hasher);
+            source.hash(&mut hasher);
+        }
+        NodeType::AdFilter { rule, .. } => {
+            "AdFilter".hash(&mut hasher);
+            rule.hash(&mut hasher);
+        }
+        NodeType::WebApi { method, .. } => {
+            "WebApi".hash(&mut hasher);
+            method.hash(&mut hasher);
+        }
+        NodeType::JsBuiltin { method, .. } => {
+            "JsBuiltin".hash(&mut hasher);
+            method.hash(&mut hasher);
+        }
+        // Unit-like variants carry no recording-specific state beyond their own identity.
+        other => variant_name(&format!("{:?}", other)).hash(&mut hasher),
+    }
+
+    hasher.finish()
+}
+
+/// The sorted multiset of incident edge type variants (direction-tagged), used as part of a
+/// node's content signature.
+fn incident_edge_multiset(graph: &PageGraph, node_id: NodeId) -> Vec<String> {
+    let mut incident: Vec<String> = graph
+        .graph
+        .edges_directed(node_id, Direction::Outgoing)
+        .map(|(_a, _b, edge_id)| format!("out:{}", variant_name(&format!("{:?}", graph.edges.get(edge_id).unwrap().edge_type))))
+        .chain(
+            graph
+                .graph
+                .edges_directed(node_id, Direction::Incoming)
+                .map(|(_a, _b, edge_id)| format!("in:{}", variant_name(&format!("{:?}", graph.edges.get(edge_id).unwrap().edge_type)))),
+        )
+        .collect();
+    incident.sort();
+    incident
+}
+
+#[cfg(test)]
+#[path = "graph_test_fixtures.rs"]
+mod fixtures;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Edge, Node};
+    use crate::types::EdgeType;
+
+    use petgraph::graphmap::DiGraphMap;
+
+    use super::fixtures::sample_graph;
+
+    #[test]
+    fn diffing_a_graph_against_itself_is_empty() {
+        let g = sample_graph();
+        let diff = g.diff(&g);
+
+        assert!(diff.removed_nodes.is_empty());
+        assert!(diff.added_nodes.is_empty());
+        assert!(diff.removed_edges.is_empty());
+        assert!(diff.added_edges.is_empty());
+        assert!(diff.changed_nodes.is_empty());
+    }
+
+    #[test]
+    fn a_different_backend_node_id_does_not_make_an_element_look_removed() {
+        let mut a = sample_graph();
+        let mut b = sample_graph();
+
+        match &mut a.nodes.get_mut(&NodeId::from(0)).unwrap().node_type {
+            NodeType::HtmlElement { node_id, .. } => *node_id = 42,
+            _ => unreachable!(),
+        }
+        match &mut b.nodes.get_mut(&NodeId::from(0)).unwrap().node_type {
+            NodeType::HtmlElement { node_id, .. } => *node_id = 999,
+            _ => unreachable!(),
+        }
+
+        let diff = a.diff(&b);
+
+        assert!(diff.removed_nodes.is_empty());
+        assert!(diff.added_nodes.is_empty());
+    }
+
+    /// Two `<div>`s, each wired to one of two distinguishable `TextNode`s, so the divs share a
+    /// single full content signature (same tag, same incident edge types) while the text nodes
+    /// each have a unique one. `swap_divs` controls which div is wired to which text node, so a
+    /// caller can build two graphs that are the same shape modulo which interchangeable div plays
+    /// which role.
+    fn two_divs_one_text_each(swap_divs: bool) -> PageGraph {
+        let div_a = NodeId::from(0);
+        let div_b = NodeId::from(1);
+        let text_left = NodeId::from(2);
+        let text_right = NodeId::from(3);
+
+        let mut nodes = HashMap::new();
+        for (id, html_node_id) in [(div_a, 1), (div_b, 2)] {
+            nodes.insert(
+                id,
+                Node {
+                    node_timestamp: 0,
+                    node_type: NodeType::HtmlElement {
+                        node_id: html_node_id,
+                        tag_name: "div".to_string(),
+                        is_deleted: false,
+                    },
+                },
+            );
+        }
+        nodes.insert(
+            text_left,
+            Node {
+                node_timestamp: 0,
+                node_type: NodeType::TextNode {
+                    node_id: 10,
+                    text: Some("left".to_string()),
+                    is_deleted: false,
+                },
+            },
+        );
+        nodes.insert(
+            text_right,
+            Node {
+                node_timestamp: 0,
+                node_type: NodeType::TextNode {
+                    node_id: 11,
+                    text: Some("right".to_string()),
+                    is_deleted: false,
+                },
+            },
+        );
+
+        let (left_parent, right_parent) = if swap_divs { (div_b, div_a) } else { (div_a, div_b) };
+
+        let mut edges = HashMap::new();
+        edges.insert(
+            EdgeId::from(0),
+            Edge {
+                edge_timestamp: Some(0),
+                edge_type: EdgeType::RequestStart {
+                    request_type: "child".to_string(),
+                },
+            },
+        );
+        edges.insert(
+            EdgeId::from(1),
+            Edge {
+                edge_timestamp: Some(0),
+                edge_type: EdgeType::RequestStart {
+                    request_type: "child".to_string(),
+                },
+            },
+        );
+
+        let mut graph = DiGraphMap::new();
+        for id in [div_a, div_b, text_left, text_right] {
+            graph.add_node(id);
+        }
+        graph.add_edge(left_parent, text_left, EdgeId::from(0));
+        graph.add_edge(right_parent, text_right, EdgeId::from(1));
+
+        PageGraph { edges, nodes, graph }
+    }
+
+    #[test]
+    fn disambiguates_same_signature_nodes_by_neighborhood_instead_of_node_id() {
+        // `a` and `b` are the same shape, but whichever div is physically wired to "left" versus
+        // "right" is swapped between them. A naive sort-and-zip pairing of the two same-signature
+        // divs would pick the wrong one on at least one side, reporting spurious added/removed
+        // edges even though nothing really changed.
+        let a = two_divs_one_text_each(false);
+        let b = two_divs_one_text_each(true);
+
+        let diff = a.diff(&b);
+
+        assert!(diff.removed_nodes.is_empty(), "{:?}", diff.removed_nodes);
+        assert!(diff.added_nodes.is_empty(), "{:?}", diff.added_nodes);
+        assert!(diff.removed_edges.is_empty(), "{:?}", diff.removed_edges);
+        assert!(diff.added_edges.is_empty(), "{:?}", diff.added_edges);
+        assert!(diff.changed_nodes.is_empty(), "{:?}", diff.changed_nodes);
+    }
+}
@@ -0,0 +1,209 @@
+//! Export a `PageGraph` to [GraphViz DOT](https://graphviz.org/doc/info/lang.html) for visual
+//! inspection.
+
+use std::io;
+
+use crate::graph::{Edge, NodeId, PageGraph};
+use crate::types::{EdgeType, NodeType};
+
+impl PageGraph {
+    /// Renders the full graph as a GraphViz DOT document.
+    pub fn to_dot(&self) -> String {
+        self.to_dot_filtered(|_| true)
+    }
+
+    /// Renders the subgraph of nodes accepted by `include` (and the edges between them) as a
+    /// GraphViz DOT document. Useful for e.g. emitting only the causal closure of one node, as
+    /// returned by `all_downstream_effects_of`.
+    pub fn to_dot_filtered<F: Fn(NodeId) -> bool>(&self, include: F) -> String {
+        let mut buf = Vec::new();
+        self.write_dot_filtered(&mut buf, include)
+            .expect("writing DOT to an in-memory buffer cannot fail");
+        String::from_utf8(buf).expect("DOT output is not valid UTF-8")
+    }
+
+    /// Streams the full graph as a GraphViz DOT document to `w`.
+    pub fn write_dot<W: io::Write>(&self, w: &mut W) -> io::Result<()> {
+        self.write_dot_filtered(w, |_| true)
+    }
+
+    /// Streams the subgraph of nodes accepted by `include` (and the edges between them) as a
+    /// GraphViz DOT document to `w`.
+    pub fn write_dot_filtered<W: io::Write, F: Fn(NodeId) -> bool>(
+        &self,
+        w: &mut W,
+        include: F,
+    ) -> io::Result<()> {
+        writeln!(w, "digraph PageGraph {{")?;
+
+        for (node_id, node) in self.nodes.iter() {
+            if !include(*node_id) {
+                continue;
+            }
+
+            writeln!(
+                w,
+                "  n{} [label=\"{}\", style=filled, fillcolor=\"{}\"];",
+                node_id.index(),
+                escape_label(&node_label(&node.node_type)),
+                node_color(&node.node_type),
+            )?;
+        }
+
+        for (a, b, edge_id) in self.graph.all_edges() {
+            if !include(a) || !include(b) {
+                continue;
+            }
+
+            let edge = self.edges.get(edge_id).expect("dangling edge id in graph");
+            writeln!(
+                w,
+                "  n{} -> n{} [label=\"{}\", color=\"{}\"];",
+                a.index(),
+                b.index(),
+                escape_label(&edge_label(edge)),
+                edge_color(&edge.edge_type),
+            )?;
+        }
+
+        writeln!(w, "}}")
+    }
+}
+
+/// A short, human-readable rendering of a node's type: a tag name, a url, a script hash, etc.
+pub(crate) fn node_label(node_type: &NodeType) -> String {
+    match node_type {
+        NodeType::HtmlElement { tag_name, .. } => format!("<{}>", tag_name),
+        NodeType::TextNode { .. } => "#text".to_string(),
+        NodeType::DomRoot { url: Some(url), .. } => format!("DomRoot\n{}", url),
+        NodeType::DomRoot { .. } => "DomRoot".to_string(),
+        NodeType::Resource { url } => format!("Resource\n{}", url),
+        NodeType::Script { .. } => format!("Script\n#{:016x}", content_hash(node_type)),
+        other => variant_name(&format!("{:?}", other)),
+    }
+}
+
+/// A short rendering of an edge's type and, if present, the timestamp it fired at.
+pub(crate) fn edge_label(edge: &Edge) -> String {
+    let variant = variant_name(&format!("{:?}", edge.edge_type));
+    match edge.edge_timestamp {
+        Some(ts) => format!("{}\n@{}", variant, ts),
+        None => variant,
+    }
+}
+
+/// GraphViz fill color for a node, grouped by the kind of side effect it represents.
+fn node_color(node_type: &NodeType) -> &'static str {
+    match node_type {
+        NodeType::Resource { .. } => "lightskyblue",
+        NodeType::Script { .. } => "khaki",
+        NodeType::HtmlElement { .. } => "palegreen",
+        NodeType::TextNode { .. } => "honeydew",
+        NodeType::DomRoot { .. } => "orange",
+        NodeType::FrameOwner { .. } | NodeType::RemoteFrame { .. } => "plum",
+        NodeType::Storage {}
+        | NodeType::LocalStorage {}
+        | NodeType::SessionStorage {}
+        | NodeType::CookieJar {} => "lightgrey",
+        NodeType::AdFilter { .. } | NodeType::TrackerFilter | NodeType::FingerprintingFilter => {
+            "salmon"
+        }
+        NodeType::BraveShields {}
+        | NodeType::AdsShield {}
+        | NodeType::TrackersShield {}
+        | NodeType::JavascriptShield {}
+        | NodeType::FingerprintingShield {} => "lightcoral",
+        _ => "white",
+    }
+}
+
+/// GraphViz color for an edge, grouped by the kind of action it represents.
+fn edge_color(edge_type: &EdgeType) -> &'static str {
+    match edge_type {
+        EdgeType::RequestStart { .. } => "blue",
+        EdgeType::RequestComplete { .. } => "darkgreen",
+        EdgeType::Structure { .. } => "gray40",
+        _ => "black",
+    }
+}
+
+/// Hashes the full `Debug` rendering of a node type, used as a stand-in content fingerprint for
+/// node kinds (like `Script`) that are best labeled by a short hash rather than their full source.
+fn content_hash(node_type: &NodeType) -> u64 {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = DefaultHasher::new();
+    format!("{:?}", node_type).hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Pulls the bare variant name (e.g. `"RequestStart"`) out of a `{:?}`-formatted enum value.
+pub(crate) fn variant_name(debug_repr: &str) -> String {
+    debug_repr
+        .split(|c| c == ' ' || c == '{' || c == '(')
+        .next()
+        .unwrap_or(debug_repr)
+        .to_string()
+}
+
+/// Escapes a label so it is safe to embed inside a DOT `"..."` string literal.
+pub(crate) fn escape_label(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+#[path = "graph_test_fixtures.rs"]
+mod fixtures;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{Edge, EdgeId, Node, NodeId};
+
+    use super::fixtures::sample_graph;
+
+    #[test]
+    fn escape_label_escapes_backslashes_quotes_and_newlines() {
+        assert_eq!(escape_label("a\\b\"c\nd"), "a\\\\b\\\"c\\nd");
+    }
+
+    #[test]
+    fn to_dot_filtered_drops_excluded_nodes_and_their_edges() {
+        let mut g = sample_graph();
+
+        let other = NodeId::from(2);
+        let other_edge = EdgeId::from(1);
+        g.nodes.insert(
+            other,
+            Node {
+                node_timestamp: 2,
+                node_type: NodeType::HtmlElement {
+                    node_id: 7,
+                    tag_name: "div".to_string(),
+                    is_deleted: false,
+                },
+            },
+        );
+        g.edges.insert(
+            other_edge,
+            Edge {
+                edge_timestamp: Some(2),
+                edge_type: EdgeType::RequestStart {
+                    request_type: "nested".to_string(),
+                },
+            },
+        );
+        g.graph.add_node(other);
+        g.graph.add_edge(NodeId::from(1), other, other_edge);
+
+        let dot = g.to_dot_filtered(|id| id != other);
+
+        assert!(dot.contains("n0"));
+        assert!(dot.contains("n1"));
+        assert!(!dot.contains("n2"));
+        assert!(!dot.contains("n1 -> n2"));
+    }
+}
@@ -0,0 +1,270 @@
+//! Subgraph pattern matching over a `PageGraph`.
+//!
+//! A `Pattern` is a set of pattern-nodes (each carrying a predicate on `NodeType`) connected by
+//! pattern-edges (each carrying a predicate on `EdgeType`). `PageGraph::find_pattern` locates
+//! every occurrence of a pattern via VF2-style subgraph isomorphism: it grows a partial injective
+//! mapping from pattern nodes to `NodeId`s one node at a time, backtracking whenever a candidate
+//! fails a node or edge predicate.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::graph::{EdgeId, NodeId, PageGraph};
+use crate::types::{EdgeType, NodeType};
+
+use petgraph::Direction;
+
+/// Identifies a node within a `Pattern`, independent of any `PageGraph` it is matched against.
+pub type PatternNodeId = usize;
+
+struct PatternEdge {
+    from: PatternNodeId,
+    to: PatternNodeId,
+    predicate: Box<dyn Fn(&EdgeType) -> bool>,
+}
+
+/// A causal shape to search for inside a `PageGraph`: a set of pattern-nodes, each with a
+/// predicate on `NodeType`, connected by directed pattern-edges, each with a predicate on
+/// `EdgeType`.
+#[derive(Default)]
+pub struct Pattern {
+    node_predicates: Vec<Box<dyn Fn(&NodeType) -> bool>>,
+    edges: Vec<PatternEdge>,
+}
+
+impl Pattern {
+    pub fn new() -> Self {
+        Pattern {
+            node_predicates: vec![],
+            edges: vec![],
+        }
+    }
+
+    /// Adds a pattern-node matching any `NodeId` whose `NodeType` satisfies `predicate`, and
+    /// returns the `PatternNodeId` to refer to it by when adding pattern-edges.
+    pub fn add_node<F: Fn(&NodeType) -> bool + 'static>(&mut self, predicate: F) -> PatternNodeId {
+        self.node_predicates.push(Box::new(predicate));
+        self.node_predicates.len() - 1
+    }
+
+    /// Adds a directed pattern-edge from `from` to `to`, matching any edge whose `EdgeType`
+    /// satisfies `predicate`.
+    pub fn add_edge<F: Fn(&EdgeType) -> bool + 'static>(
+        &mut self,
+        from: PatternNodeId,
+        to: PatternNodeId,
+        predicate: F,
+    ) {
+        self.edges.push(PatternEdge {
+            from,
+            to,
+            predicate: Box::new(predicate),
+        });
+    }
+}
+
+impl PageGraph {
+    /// Finds every occurrence of `pattern` in this graph, returning one mapping from
+    /// `PatternNodeId` to `NodeId` per match. Mappings are deduplicated.
+    pub fn find_pattern(&self, pattern: &Pattern) -> Vec<HashMap<PatternNodeId, NodeId>> {
+        let mut results = Vec::new();
+        let mut mapping = HashMap::new();
+        let mut used = HashSet::new();
+
+        self.extend_match(pattern, &mut mapping, &mut used, &mut results);
+
+        let mut seen = HashSet::new();
+        results.retain(|mapping| {
+            let mut key: Vec<(PatternNodeId, NodeId)> = mapping.iter().map(|(k, v)| (*k, *v)).collect();
+            key.sort_by_key(|(pattern_node, _)| *pattern_node);
+            seen.insert(key)
+        });
+        results
+    }
+
+    /// Recursively grows `mapping` by assigning the next unmapped pattern-node to every
+    /// `NodeId` candidate that satisfies its node predicate and every already-mapped
+    /// pattern-edge incident to it, backtracking on failure.
+    fn extend_match(
+        &self,
+        pattern: &Pattern,
+        mapping: &mut HashMap<PatternNodeId, NodeId>,
+        used: &mut HashSet<NodeId>,
+        results: &mut Vec<HashMap<PatternNodeId, NodeId>>,
+    ) {
+        if mapping.len() == pattern.node_predicates.len() {
+            results.push(mapping.clone());
+            return;
+        }
+
+        let next = next_unmapped_pattern_node(pattern, mapping);
+        let predicate = &pattern.node_predicates[next];
+
+        for candidate in self.candidates_for(pattern, mapping, next) {
+            if used.contains(&candidate) {
+                continue;
+            }
+
+            let node = match self.nodes.get(&candidate) {
+                Some(node) => node,
+                None => continue,
+            };
+            if !predicate(&node.node_type) {
+                continue;
+            }
+            if !self.edges_consistent(pattern, mapping, next, candidate) {
+                continue;
+            }
+
+            mapping.insert(next, candidate);
+            used.insert(candidate);
+
+            self.extend_match(pattern, mapping, used, results);
+
+            mapping.remove(&next);
+            used.remove(&candidate);
+        }
+    }
+
+    /// Candidate `NodeId`s for pattern-node `next`: nodes adjacent (in the direction the
+    /// pattern requires) to every already-mapped pattern-node connected to it, or every node
+    /// in the graph if `next` isn't yet constrained by any mapped neighbor.
+    fn candidates_for(
+        &self,
+        pattern: &Pattern,
+        mapping: &HashMap<PatternNodeId, NodeId>,
+        next: PatternNodeId,
+    ) -> Vec<NodeId> {
+        let mut constrained: Option<HashSet<NodeId>> = None;
+
+        let mut narrow = |found: HashSet<NodeId>| {
+            constrained = Some(match constrained.take() {
+                Some(existing) => &existing & &found,
+                None => found,
+            });
+        };
+
+        for edge in &pattern.edges {
+            if edge.from == next {
+                if let Some(&mapped_to) = mapping.get(&edge.to) {
+                    narrow(self.graph.neighbors_directed(mapped_to, Direction::Incoming).collect());
+                }
+            }
+            if edge.to == next {
+                if let Some(&mapped_from) = mapping.get(&edge.from) {
+                    narrow(self.graph.neighbors_directed(mapped_from, Direction::Outgoing).collect());
+                }
+            }
+        }
+
+        match constrained {
+            Some(candidates) => candidates.into_iter().collect(),
+            None => self.nodes.keys().copied().collect(),
+        }
+    }
+
+    /// Checks that every already-mapped pattern-edge incident to pattern-node `next` (now
+    /// tentatively bound to `candidate`) corresponds to a real edge in `self.graph` whose
+    /// `EdgeType` satisfies that pattern-edge's predicate.
+    fn edges_consistent(
+        &self,
+        pattern: &Pattern,
+        mapping: &HashMap<PatternNodeId, NodeId>,
+        next: PatternNodeId,
+        candidate: NodeId,
+    ) -> bool {
+        pattern.edges.iter().all(|edge| {
+            if edge.from == next {
+                match mapping.get(&edge.to) {
+                    Some(&mapped_to) => self.edge_matches(candidate, mapped_to, &edge.predicate),
+                    None => true,
+                }
+            } else if edge.to == next {
+                match mapping.get(&edge.from) {
+                    Some(&mapped_from) => self.edge_matches(mapped_from, candidate, &edge.predicate),
+                    None => true,
+                }
+            } else {
+                true
+            }
+        })
+    }
+
+    fn edge_matches<F: Fn(&EdgeType) -> bool>(&self, from: NodeId, to: NodeId, predicate: &F) -> bool {
+        self.edge_between(from, to)
+            .map_or(false, |edge_id| predicate(&self.edges.get(&edge_id).unwrap().edge_type))
+    }
+
+    fn edge_between(&self, from: NodeId, to: NodeId) -> Option<EdgeId> {
+        self.graph.edge_weight(from, to).copied()
+    }
+}
+
+/// Picks the next pattern-node to assign: one adjacent (via a pattern-edge) to the current
+/// partial mapping if one exists, otherwise the lowest-numbered unmapped pattern-node.
+fn next_unmapped_pattern_node(
+    pattern: &Pattern,
+    mapping: &HashMap<PatternNodeId, NodeId>,
+) -> PatternNodeId {
+    pattern
+        .edges
+        .iter()
+        .find_map(|edge| {
+            if mapping.contains_key(&edge.from) && !mapping.contains_key(&edge.to) {
+                Some(edge.to)
+            } else if mapping.contains_key(&edge.to) && !mapping.contains_key(&edge.from) {
+                Some(edge.from)
+            } else {
+                None
+            }
+        })
+        .unwrap_or_else(|| {
+            (0..pattern.node_predicates.len())
+                .find(|candidate| !mapping.contains_key(candidate))
+                .expect("called with a fully-mapped pattern")
+        })
+}
+
+#[cfg(test)]
+#[path = "graph_test_fixtures.rs"]
+mod fixtures;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use super::fixtures::sample_graph;
+
+    #[test]
+    fn finds_a_script_element_requesting_a_resource() {
+        let g = sample_graph();
+
+        let mut pattern = Pattern::new();
+        let script_element = pattern.add_node(|node_type| {
+            matches!(node_type, NodeType::HtmlElement { tag_name, .. } if tag_name == "script")
+        });
+        let resource = pattern.add_node(|node_type| matches!(node_type, NodeType::Resource { .. }));
+        pattern.add_edge(script_element, resource, |edge_type| {
+            matches!(edge_type, EdgeType::RequestStart { .. })
+        });
+
+        let matches = g.find_pattern(&pattern);
+
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0][&script_element], NodeId::from(0));
+        assert_eq!(matches[0][&resource], NodeId::from(1));
+    }
+
+    #[test]
+    fn does_not_match_when_the_edge_predicate_fails() {
+        let g = sample_graph();
+
+        let mut pattern = Pattern::new();
+        let script_element = pattern.add_node(|node_type| matches!(node_type, NodeType::HtmlElement { .. }));
+        let resource = pattern.add_node(|node_type| matches!(node_type, NodeType::Resource { .. }));
+        pattern.add_edge(script_element, resource, |edge_type| {
+            matches!(edge_type, EdgeType::RequestComplete { .. })
+        });
+
+        assert!(g.find_pattern(&pattern).is_empty());
+    }
+}
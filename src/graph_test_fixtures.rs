@@ -0,0 +1,56 @@
+//! Shared `PageGraph` fixtures for unit tests in the `graph_*` modules. Pulled out once the same
+//! fixture started appearing verbatim in more than one module's `tests`.
+
+use std::collections::HashMap;
+
+use crate::graph::{Edge, EdgeId, Node, NodeId, PageGraph};
+use crate::types::{EdgeType, NodeType};
+
+use petgraph::graphmap::DiGraphMap;
+
+/// A `script` element with a `src` attribute that requests a `Resource`.
+pub(crate) fn sample_graph() -> PageGraph {
+    let element = NodeId::from(0);
+    let resource = NodeId::from(1);
+    let request_start = EdgeId::from(0);
+
+    let mut nodes = HashMap::new();
+    nodes.insert(
+        element,
+        Node {
+            node_timestamp: 0,
+            node_type: NodeType::HtmlElement {
+                node_id: 42,
+                tag_name: "script".to_string(),
+                is_deleted: false,
+            },
+        },
+    );
+    nodes.insert(
+        resource,
+        Node {
+            node_timestamp: 1,
+            node_type: NodeType::Resource {
+                url: "https://example.com/a.js".to_string(),
+            },
+        },
+    );
+
+    let mut edges = HashMap::new();
+    edges.insert(
+        request_start,
+        Edge {
+            edge_timestamp: Some(1),
+            edge_type: EdgeType::RequestStart {
+                request_type: "script".to_string(),
+            },
+        },
+    );
+
+    let mut graph = DiGraphMap::new();
+    graph.add_node(element);
+    graph.add_node(resource);
+    graph.add_edge(element, resource, request_start);
+
+    PageGraph { edges, nodes, graph }
+}